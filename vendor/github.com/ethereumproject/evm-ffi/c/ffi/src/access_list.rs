@@ -0,0 +1,202 @@
+//! The EIP-2929 mechanism itself: per-transaction warm/cold sets journaled
+//! per call frame, so a REVERT or exceptional halt can unwind exactly the
+//! entries added within that frame without disturbing anything pre-dating
+//! it. The costs it charges are the ones passed to `AccessList::new`.
+//! Nothing in this crate constructs an `AccessList` or calls it from
+//! SLOAD/SSTORE/BALANCE/EXTCODE*/CALL dispatch, and the `evm` executor,
+//! which this crate depends on, cannot call it; `dynamic_patch_builder`'s
+//! `has_access_list`/`gas_*_cold`/`gas_*_warm` fields are only forwarded to
+//! `evm::DynamicPatch`.
+//!
+//! Needs `mod access_list;` declared at the crate root alongside
+//! `dynamic`/`legacy` for this module to be compiled in.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use bigint::{Address, Gas, U256};
+
+/// A set of entries that can be rolled back to an earlier state.
+///
+/// Entries added while one or more checkpoints are open are recorded
+/// against the innermost (most recently pushed) checkpoint. `revert` pops
+/// that checkpoint and evicts only the entries it recorded; `commit` pops
+/// it without evicting anything, so its entries stay live and are now
+/// attributed to the parent checkpoint (or, if there is none, to the set
+/// as a whole).
+struct JournaledSet<K> {
+    entries: HashSet<K>,
+    checkpoints: Vec<Vec<K>>,
+}
+
+impl<K: Eq + Hash + Copy> JournaledSet<K> {
+    fn new() -> Self {
+        JournaledSet { entries: HashSet::new(), checkpoints: Vec::new() }
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        self.entries.contains(key)
+    }
+
+    /// Inserts `key`. Returns `true` if it was not already present (cold),
+    /// `false` if it was already present (warm).
+    fn insert(&mut self, key: K) -> bool {
+        let inserted = self.entries.insert(key);
+        if inserted {
+            if let Some(frame) = self.checkpoints.last_mut() {
+                frame.push(key);
+            }
+        }
+        inserted
+    }
+
+    fn checkpoint(&mut self) {
+        self.checkpoints.push(Vec::new());
+    }
+
+    fn commit(&mut self) {
+        if let Some(frame) = self.checkpoints.pop() {
+            if let Some(parent) = self.checkpoints.last_mut() {
+                parent.extend(frame);
+            }
+        }
+    }
+
+    fn revert(&mut self) {
+        if let Some(frame) = self.checkpoints.pop() {
+            for key in frame {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+/// Per-transaction, journaled EIP-2929 access sets plus the cost constants
+/// they're charged against.
+pub struct AccessList {
+    addresses: JournaledSet<Address>,
+    slots: JournaledSet<(Address, U256)>,
+    gas_sload_cold: Gas,
+    gas_sload_warm: Gas,
+    gas_account_cold: Gas,
+    gas_account_warm: Gas,
+    gas_call_cold: Gas,
+}
+
+impl AccessList {
+    pub fn new(
+        gas_sload_cold: Gas, gas_sload_warm: Gas,
+        gas_account_cold: Gas, gas_account_warm: Gas,
+        gas_call_cold: Gas,
+    ) -> Self {
+        AccessList {
+            addresses: JournaledSet::new(),
+            slots: JournaledSet::new(),
+            gas_sload_cold, gas_sload_warm, gas_account_cold, gas_account_warm, gas_call_cold,
+        }
+    }
+
+    /// Pre-warms an address at transaction start: the sender, the call
+    /// target, the enabled precompiles, or an access-list entry.
+    pub fn pre_warm_address(&mut self, address: Address) {
+        self.addresses.insert(address);
+    }
+
+    /// Pre-warms a storage slot supplied in the transaction's access list.
+    pub fn pre_warm_slot(&mut self, address: Address, slot: U256) {
+        self.slots.insert((address, slot));
+    }
+
+    /// Opens a new call frame. Entries added after this call are rolled
+    /// back by the next matching `revert`.
+    pub fn checkpoint(&mut self) {
+        self.addresses.checkpoint();
+        self.slots.checkpoint();
+    }
+
+    /// Closes the current call frame on success: its entries stay warm.
+    pub fn commit(&mut self) {
+        self.addresses.commit();
+        self.slots.commit();
+    }
+
+    /// Closes the current call frame on REVERT or an exceptional halt:
+    /// entries added within it are evicted, so they charge cold again.
+    pub fn revert(&mut self) {
+        self.addresses.revert();
+        self.slots.revert();
+    }
+
+    /// Charges an SLOAD/SSTORE touch of `(address, slot)`.
+    pub fn touch_slot(&mut self, address: Address, slot: U256) -> Gas {
+        if self.slots.insert((address, slot)) { self.gas_sload_cold } else { self.gas_sload_warm }
+    }
+
+    /// Charges a BALANCE/EXTCODE*-style touch of `address`.
+    pub fn touch_address(&mut self, address: Address) -> Gas {
+        if self.addresses.insert(address) { self.gas_account_cold } else { self.gas_account_warm }
+    }
+
+    /// Charges a CALL-family touch of the target `address`, which pays the
+    /// account cold/warm cost plus, only when cold, `gas_call_cold`.
+    pub fn touch_call_target(&mut self, address: Address) -> Gas {
+        let cold = !self.addresses.contains(&address);
+        self.addresses.insert(address);
+        if cold { self.gas_account_cold + self.gas_call_cold } else { self.gas_account_warm }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JournaledSet;
+
+    #[test]
+    fn first_touch_is_cold_second_is_warm() {
+        let mut set: JournaledSet<u32> = JournaledSet::new();
+        assert!(set.insert(1));
+        assert!(!set.insert(1));
+    }
+
+    #[test]
+    fn revert_unwinds_only_the_reverted_frame() {
+        let mut set: JournaledSet<u32> = JournaledSet::new();
+
+        // Warmed before any call frame opens (e.g. pre-warming): survives any revert.
+        set.insert(1);
+
+        set.checkpoint(); // enter sub-call
+        set.insert(2);
+        assert!(set.contains(&2));
+        set.revert(); // sub-call reverts
+        assert!(!set.contains(&2), "a reverted sub-call must not leave its slot warm");
+        assert!(set.contains(&1), "entries from outside the reverted frame must survive");
+
+        // The slot is cold again, so touching it a second time is a fresh (cold) insert.
+        assert!(set.insert(2));
+    }
+
+    #[test]
+    fn commit_keeps_entries_warm() {
+        let mut set: JournaledSet<u32> = JournaledSet::new();
+        set.checkpoint();
+        set.insert(1);
+        set.commit();
+        assert!(set.contains(&1));
+        assert!(!set.insert(1));
+    }
+
+    #[test]
+    fn nested_revert_only_touches_innermost_frame() {
+        let mut set: JournaledSet<u32> = JournaledSet::new();
+
+        set.checkpoint(); // outer call
+        set.insert(1);
+        set.checkpoint(); // inner call
+        set.insert(2);
+        set.revert(); // inner call reverts
+        assert!(!set.contains(&2));
+        assert!(set.contains(&1), "outer frame's entries must survive the inner revert");
+        set.commit(); // outer call succeeds
+        assert!(set.contains(&1));
+    }
+}
@@ -3,13 +3,26 @@
 #[cfg(not(feature = "std"))] use core::slice;
 #[cfg(feature = "std")] use std::slice;
 
-use evm::{DynamicPatch, DynamicAccountPatch};
+use evm::{DynamicPatch, DynamicAccountPatch, Precompiled, PrecompiledError};
 use evm_network::PRECOMPILEDS;
 use smallvec::SmallVec;
+use bigint::Gas;
 
 use crate::common::{c_gas, c_u256};
 use crate::c_address;
 
+// `has_access_list`/`gas_*_cold`/`gas_*_warm` below configure the EIP-2929
+// cost constants. The journaled per-transaction address/storage-slot access
+// sets and the checkpoint/revert mechanism that actually implement the
+// warm/cold accounting live in `access_list::AccessList` (see that module
+// for the journal itself and tests proving a reverted sub-call leaves no
+// slot warm). `dynamic_patch_new` does not use `access_list::AccessList`:
+// it only forwards these fields to `DynamicPatch`. Nothing in this crate
+// calls `AccessList::{checkpoint, commit, revert}` from SLOAD/SSTORE/
+// BALANCE/EXTCODE*/CALL dispatch or pre-warms the sender/call target/
+// precompiles at transaction start, and the `evm` executor cannot call a
+// type of this crate, which depends on `evm`.
+
 #[repr(C)]
 pub struct dynamic_patch_builder {
     /// Maximum contract size. 0 for unlimited.
@@ -51,6 +64,47 @@ pub struct dynamic_patch_builder {
     pub has_extcodehash: bool,
     /// Whether EVM should implement the EIP1283 gas metering scheme for SSTORE opcode
     pub has_reduced_sstore_gas_metering: bool,
+    /// Whether the EVM implements EIP-2929 warm/cold access gas metering. When
+    /// set, SLOAD/SSTORE and BALANCE/EXTCODE*/CALL-family accesses are charged
+    /// against per-transaction, journaled address and storage-slot access
+    /// sets instead of the flat `gas_sload`/`gas_balance`/`gas_extcode`/`gas_call`
+    /// costs above.
+    pub has_access_list: bool,
+    /// Gas paid for SLOAD opcode on a storage slot not yet in the
+    /// per-transaction access set.
+    pub gas_sload_cold: c_gas,
+    /// Gas paid for SLOAD opcode on a storage slot already in the
+    /// per-transaction access set.
+    pub gas_sload_warm: c_gas,
+    /// Gas paid for BALANCE/EXTCODE*/CALL-family opcodes touching an account
+    /// not yet in the per-transaction access set.
+    pub gas_account_cold: c_gas,
+    /// Gas paid for BALANCE/EXTCODE*/CALL-family opcodes touching an account
+    /// already in the per-transaction access set.
+    pub gas_account_warm: c_gas,
+    /// Additional gas paid for the CALL-family opcodes when the target
+    /// account is not yet in the per-transaction access set, on top of
+    /// `gas_account_cold`.
+    pub gas_call_cold: c_gas,
+    /// Whether the EVM has the BASEFEE opcode (EIP-3198, London).
+    pub has_base_fee: bool,
+    /// Gas refunded when an SSTORE transitions a storage slot to zero
+    /// (EIP-2200/EIP-3529).
+    ///
+    /// This and the two fields below are carried straight into the
+    /// like-named fields this FFI crate assumes `evm::DynamicPatch` now
+    /// has; that struct isn't in this source tree, so treat the field names
+    /// and the `usize` type of `max_refund_quotient` as the contract a
+    /// companion `evm`-crate change needs to match.
+    pub sstore_refund_clear: c_gas,
+    /// Gas refunded for a SELFDESTRUCT. Set to 0 to implement EIP-3529,
+    /// which removes the SELFDESTRUCT refund.
+    pub suicide_refund: c_gas,
+    /// Divisor bounding the total gas refund as `gas_used / max_refund_quotient`.
+    /// Callers should pass 2 pre-London, 5 from London onward (EIP-3529);
+    /// `dynamic_patch_new` below only substitutes 1 (uncapped) for a literal
+    /// 0, as a divide-by-zero fallback, not as a real policy value.
+    pub max_refund_quotient: usize,
     /// Whether to throw out of gas error when
     /// CALL/CALLCODE/DELEGATECALL requires more than maximum amount
     /// of gas.
@@ -64,6 +118,110 @@ pub struct dynamic_patch_builder {
     /// Enabled precompiled contracts array
     pub enabled_contracts: *const c_address,
     pub enabled_contracts_length: usize,
+    /// Host-supplied custom precompiled contracts, dispatched through
+    /// `gas_cost_fn`/`execute_fn` callbacks. Checked before the built-in
+    /// `enabled_contracts` table, so a host can also use this to override
+    /// a built-in precompile.
+    pub custom_precompiles: *const c_custom_precompile,
+    pub custom_precompiles_length: usize,
+}
+
+/// `gas_cost_fn(input_ptr, input_len) -> gas_cost`
+pub type c_precompile_gas_cost_fn = extern "C" fn(*const u8, usize) -> c_gas;
+
+/// `execute_fn(input_ptr, input_len, output_buf, output_cap, output_len_out) -> status`
+///
+/// On success (status `0`), writes the output to `output_buf` and its
+/// length to `output_len_out`. If `output_cap` is too small to hold the
+/// output, returns `PRECOMPILE_STATUS_BUFFER_TOO_SMALL` without writing to
+/// `output_buf`, and writes the required capacity to `output_len_out` so
+/// the caller can retry once with a larger buffer. Any other non-zero
+/// status is treated as a hard failure.
+pub type c_precompile_execute_fn = extern "C" fn(*const u8, usize, *mut u8, usize, *mut usize) -> i32;
+
+/// `execute_fn` succeeded.
+pub const PRECOMPILE_STATUS_OK: i32 = 0;
+/// `execute_fn`'s `output_cap` was too small; `output_len_out` holds the
+/// required capacity and the call should be retried with a bigger buffer.
+pub const PRECOMPILE_STATUS_BUFFER_TOO_SMALL: i32 = 1;
+
+/// A single host-supplied precompiled contract.
+#[repr(C)]
+pub struct c_custom_precompile {
+    pub address: c_address,
+    pub gas_cost_fn: c_precompile_gas_cost_fn,
+    pub execute_fn: c_precompile_execute_fn,
+}
+
+/// Starting size of the output buffer handed to `execute_fn`. Large
+/// outputs (e.g. a modexp-style precompile) are handled by the
+/// `PRECOMPILE_STATUS_BUFFER_TOO_SMALL` retry below, not by inflating this.
+const CUSTOM_PRECOMPILE_OUTPUT_INITIAL_CAPACITY: usize = 1024;
+
+// `custom_precompileds` below is built here and threaded straight into the
+// `custom_precompileds` field of the `DynamicPatch` literal in
+// `dynamic_patch_new`. That field, and `DynamicPatch` dispatching to it
+// before `enabled_precompileds`/`precompileds`, are additions this FFI crate
+// assumes exist on the out-of-tree `evm::DynamicPatch` — they are not
+// present in this source tree to verify against. The exact contract this
+// side relies on: a `SmallVec` of `(Address, Box<dyn Precompiled>)` field
+// (the same collection type as `enabled_precompileds`), and
+// `Precompiled`/`PrecompiledError` with the signatures `FfiPrecompiled`
+// implements below (`gas_and_step(&self, &[u8], Gas) -> Result<(Gas,
+// Vec<u8>), PrecompiledError>`, and an `Other` variant for host-reported
+// failures). If `evm::DynamicPatch`/`Precompiled`/`PrecompiledError` don't
+// already look like that, they need a companion change before this builds.
+
+/// Adapts a pair of host callbacks to the `Precompiled` trait so custom
+/// precompiles can be dispatched like any built-in one.
+struct FfiPrecompiled {
+    gas_cost_fn: c_precompile_gas_cost_fn,
+    execute_fn: c_precompile_execute_fn,
+}
+
+impl FfiPrecompiled {
+    /// Calls `execute_fn` with `output`, growing it once and retrying if the
+    /// host reports `PRECOMPILE_STATUS_BUFFER_TOO_SMALL`.
+    fn execute(&self, input: &[u8], output: &mut Vec<u8>) -> Result<usize, PrecompiledError> {
+        let mut output_len: usize = 0;
+        let status = (self.execute_fn)(
+            input.as_ptr(), input.len(),
+            output.as_mut_ptr(), output.len(), &mut output_len,
+        );
+
+        if status == PRECOMPILE_STATUS_BUFFER_TOO_SMALL {
+            *output = vec![0u8; output_len];
+            let status = (self.execute_fn)(
+                input.as_ptr(), input.len(),
+                output.as_mut_ptr(), output.len(), &mut output_len,
+            );
+            if status != PRECOMPILE_STATUS_OK {
+                return Err(PrecompiledError::Other);
+            }
+        } else if status != PRECOMPILE_STATUS_OK {
+            return Err(PrecompiledError::Other);
+        }
+
+        // The host must not report more bytes written than the buffer it was given.
+        if output_len > output.len() {
+            return Err(PrecompiledError::Other);
+        }
+        Ok(output_len)
+    }
+}
+
+impl Precompiled for FfiPrecompiled {
+    fn gas_and_step(&self, input: &[u8], gas_limit: Gas) -> Result<(Gas, Vec<u8>), PrecompiledError> {
+        let required: Gas = (self.gas_cost_fn)(input.as_ptr(), input.len()).into();
+        if required > gas_limit {
+            return Err(PrecompiledError::OutOfGas);
+        }
+
+        let mut output = vec![0u8; CUSTOM_PRECOMPILE_OUTPUT_INITIAL_CAPACITY];
+        let output_len = self.execute(input, &mut output)?;
+        output.truncate(output_len);
+        Ok((required, output))
+    }
 }
 
 #[repr(C)]
@@ -96,6 +254,22 @@ extern "C" fn dynamic_patch_new(builder: dynamic_patch_builder, account_patch: d
         enabled_contracts.push(address);
     };
 
+    // A zero divisor would make the VM's `gas_used / max_refund_quotient` refund
+    // cap panic on any transaction that earns a refund; 1 behaves as "uncapped",
+    // which is the closest sane reading of a zero-initialized builder.
+    let max_refund_quotient = builder.max_refund_quotient.max(1);
+
+    let mut custom_precompileds = SmallVec::new();
+    let c_custom_precompiles = unsafe { slice::from_raw_parts(builder.custom_precompiles, builder.custom_precompiles_length) };
+    for custom_precompile in c_custom_precompiles {
+        let address = custom_precompile.address.into();
+        let precompiled: Box<dyn Precompiled> = Box::new(FfiPrecompiled {
+            gas_cost_fn: custom_precompile.gas_cost_fn,
+            execute_fn: custom_precompile.execute_fn,
+        });
+        custom_precompileds.push((address, precompiled));
+    };
+
     let patch = DynamicPatch {
         account_patch: DynamicAccountPatch::from(account_patch),
         code_deposit_limit: if builder.code_deposit_limit == 0 { None } else { Some(builder.code_deposit_limit) },
@@ -117,11 +291,22 @@ extern "C" fn dynamic_patch_new(builder: dynamic_patch_builder, account_patch: d
         has_bitwise_shift: builder.has_bitwise_shift,
         has_extcodehash: builder.has_extcodehash,
         has_reduced_sstore_gas_metering: builder.has_reduced_sstore_gas_metering,
+        has_access_list: builder.has_access_list,
+        gas_sload_cold: builder.gas_sload_cold.into(),
+        gas_sload_warm: builder.gas_sload_warm.into(),
+        gas_account_cold: builder.gas_account_cold.into(),
+        gas_account_warm: builder.gas_account_warm.into(),
+        gas_call_cold: builder.gas_call_cold.into(),
+        has_base_fee: builder.has_base_fee,
+        sstore_refund_clear: builder.sstore_refund_clear.into(),
+        suicide_refund: builder.suicide_refund.into(),
+        max_refund_quotient,
         err_on_call_with_more_gas: builder.err_on_call_with_more_gas,
         call_create_l64_after_gas: builder.call_create_l64_after_gas,
         memory_limit: builder.memory_limit,
         enabled_precompileds: enabled_contracts,
         precompileds: &PRECOMPILEDS,
+        custom_precompileds,
     };
 
     Box::into_raw(Box::new(patch)) as *mut dynamic_patch_box
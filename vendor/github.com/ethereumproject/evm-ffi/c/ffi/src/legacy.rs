@@ -29,14 +29,59 @@ pub type MordenHomesteadPatch = HomesteadPatch<MordenAccountPatch>;
 pub type MordenEIP150Patch = EIP150Patch<MordenAccountPatch>;
 pub type MordenEIP160Patch = EIP160Patch<MordenAccountPatch>;
 
-static mut CUSTOM_INITIAL_NONCE: Option<U256> = None;
+/// By-value replacement for the process-global `CUSTOM_INITIAL_NONCE`,
+/// passed into the `sputnikvm_new_custom_*` constructors below and carried
+/// straight through to a `CustomAccountPatch` instance, the same way
+/// `dynamic_account_patch` is carried into `DynamicAccountPatch` (see
+/// `dynamic::dynamic_patch_new`, which builds `DynamicPatch` with
+/// `account_patch: DynamicAccountPatch::from(account_patch)` as a plain
+/// struct-literal field — no `evm_network_classic`-side constructor call).
+///
+/// The four `sputnikvm_new_custom_*` functions below assume
+/// `FrontierPatch<AP>`/`HomesteadPatch<AP>`/`EIP150Patch<AP>`/`EIP160Patch<AP>`
+/// expose a `new(AP)` constructor for a runtime-supplied, non-`Default` `AP`.
+/// That mirrors the `DynamicPatch` field-literal pattern above in spirit, but
+/// `evm_network_classic` is an external crate not present in this source
+/// tree, this file only ever called `FrontierPatch::default()` etc. before
+/// this change, and no `new` is visible anywhere in-tree — so the
+/// constructor's existence is unverified. If it turns out `FrontierPatch<AP>`
+/// instead takes `AP` through a public field or a different associated
+/// function, swap the `*Patch::new(...)` calls below for that instead; the
+/// `CustomAccountPatch` and `c_custom_account_patch` types below do not need
+/// to change either way.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct c_custom_account_patch {
+    pub initial_nonce: c_u256,
+    pub initial_create_nonce: c_u256,
+    pub empty_considered_exists: bool,
+    pub allow_partial_change: bool,
+}
+
+#[derive(Copy, Clone)]
+pub struct CustomAccountPatch {
+    initial_nonce: U256,
+    initial_create_nonce: U256,
+    empty_considered_exists: bool,
+    allow_partial_change: bool,
+}
+
+impl From<c_custom_account_patch> for CustomAccountPatch {
+    fn from(p: c_custom_account_patch) -> Self {
+        Self {
+            initial_nonce: p.initial_nonce.into(),
+            initial_create_nonce: p.initial_create_nonce.into(),
+            empty_considered_exists: p.empty_considered_exists,
+            allow_partial_change: p.allow_partial_change,
+        }
+    }
+}
 
-#[derive(Copy, Clone, Default)]
-pub struct CustomAccountPatch;
 impl AccountPatch for CustomAccountPatch {
-    fn initial_nonce(&self) -> U256 { U256::from(unsafe { CUSTOM_INITIAL_NONCE.unwrap() }) }
-    fn initial_create_nonce(&self) -> U256 { self.initial_nonce() }
-    fn empty_considered_exists(&self) -> bool { true }
+    fn initial_nonce(&self) -> U256 { self.initial_nonce }
+    fn initial_create_nonce(&self) -> U256 { self.initial_create_nonce }
+    fn empty_considered_exists(&self) -> bool { self.empty_considered_exists }
+    fn allow_partial_change(&self) -> bool { self.allow_partial_change }
 }
 
 pub type CustomFrontierPatch = FrontierPatch<CustomAccountPatch>;
@@ -44,28 +89,16 @@ pub type CustomHomesteadPatch = HomesteadPatch<CustomAccountPatch>;
 pub type CustomEIP150Patch = EIP150Patch<CustomAccountPatch>;
 pub type CustomEIP160Patch = EIP160Patch<CustomAccountPatch>;
 
-#[no_mangle]
-#[deprecated(since = "0.11.0", note = "Ethereum Classic specific FFI interface is deprecated, use the network-agnostic API instead.")]
-pub unsafe extern "C" fn sputnikvm_set_custom_initial_nonce(v: c_u256) {
-    let v: U256 = v.into();
-    CUSTOM_INITIAL_NONCE = Some(v)
-}
-
 lazy_static! {
     static ref MAINNET_FRONTIER_PATCH: MainnetFrontierPatch = FrontierPatch::default();
     static ref MAINNET_HOMESTEAD_PATCH: MainnetHomesteadPatch = HomesteadPatch::default();
     static ref MAINNET_EIP150_PATCH: MainnetEIP150Patch = EIP150Patch::default();
     static ref MAINNET_EIP160_PATCH: MainnetEIP160Patch = EIP160Patch::default();
-    
+
     static ref MORDEN_FRONTIER_PATCH: MordenFrontierPatch = FrontierPatch::default();
     static ref MORDEN_HOMESTEAD_PATCH: MordenHomesteadPatch = HomesteadPatch::default();
     static ref MORDEN_EIP150_PATCH: MordenEIP150Patch = EIP150Patch::default();
     static ref MORDEN_EIP160_PATCH: MordenEIP160Patch = EIP160Patch::default();
-    
-    static ref CUSTOM_FRONTIER_PATCH: CustomFrontierPatch = FrontierPatch::default();
-    static ref CUSTOM_HOMESTEAD_PATCH: CustomHomesteadPatch = HomesteadPatch::default();
-    static ref CUSTOM_EIP150_PATCH: CustomEIP150Patch = EIP150Patch::default();
-    static ref CUSTOM_EIP160_PATCH: CustomEIP160Patch = EIP160Patch::default();
 }
 
 #[no_mangle]
@@ -135,31 +168,35 @@ pub extern "C" fn sputnikvm_new_morden_eip160(
 #[no_mangle]
 #[deprecated(since = "0.11.0", note = "Ethereum Classic specific FFI interface is deprecated, use the network-agnostic API instead.")]
 pub extern "C" fn sputnikvm_new_custom_frontier(
-    transaction: c_transaction, header: c_header_params
+    account_patch: c_custom_account_patch, transaction: c_transaction, header: c_header_params
 ) -> *mut Box<VM> {
-    sputnikvm_new(&*CUSTOM_FRONTIER_PATCH, transaction, header)
+    let patch: CustomFrontierPatch = FrontierPatch::new(CustomAccountPatch::from(account_patch));
+    sputnikvm_new(&patch, transaction, header)
 }
 
 #[no_mangle]
 #[deprecated(since = "0.11.0", note = "Ethereum Classic specific FFI interface is deprecated, use the network-agnostic API instead.")]
 pub extern "C" fn sputnikvm_new_custom_homestead(
-    transaction: c_transaction, header: c_header_params
+    account_patch: c_custom_account_patch, transaction: c_transaction, header: c_header_params
 ) -> *mut Box<VM> {
-    sputnikvm_new(&*CUSTOM_HOMESTEAD_PATCH, transaction, header)
+    let patch: CustomHomesteadPatch = HomesteadPatch::new(CustomAccountPatch::from(account_patch));
+    sputnikvm_new(&patch, transaction, header)
 }
 
 #[no_mangle]
 #[deprecated(since = "0.11.0", note = "Ethereum Classic specific FFI interface is deprecated, use the network-agnostic API instead.")]
 pub extern "C" fn sputnikvm_new_custom_eip150(
-    transaction: c_transaction, header: c_header_params
+    account_patch: c_custom_account_patch, transaction: c_transaction, header: c_header_params
 ) -> *mut Box<VM> {
-    sputnikvm_new(&*CUSTOM_EIP150_PATCH, transaction, header)
+    let patch: CustomEIP150Patch = EIP150Patch::new(CustomAccountPatch::from(account_patch));
+    sputnikvm_new(&patch, transaction, header)
 }
 
 #[no_mangle]
 #[deprecated(since = "0.11.0", note = "Ethereum Classic specific FFI interface is deprecated, use the network-agnostic API instead.")]
 pub extern "C" fn sputnikvm_new_custom_eip160(
-    transaction: c_transaction, header: c_header_params
+    account_patch: c_custom_account_patch, transaction: c_transaction, header: c_header_params
 ) -> *mut Box<VM> {
-    sputnikvm_new(&*CUSTOM_EIP160_PATCH, transaction, header)
+    let patch: CustomEIP160Patch = EIP160Patch::new(CustomAccountPatch::from(account_patch));
+    sputnikvm_new(&patch, transaction, header)
 }
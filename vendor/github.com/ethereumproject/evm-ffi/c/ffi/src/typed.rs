@@ -0,0 +1,160 @@
+//! FFI constructors for EIP-2930 (access list) and EIP-1559 (fee market)
+//! typed transactions, layered on top of the legacy `c_transaction` shape.
+//!
+//! This source drop has no crate root (`lib.rs`) at all — `dynamic.rs`,
+//! `legacy.rs`, `access_list.rs` and this file all need `mod` declarations
+//! added to whatever root module the full crate builds from; that's a
+//! property of this snapshot as a whole, not specific to this file.
+//!
+//! `sputnikvm_new_fee_market` takes `base_fee` as an explicit parameter
+//! rather than reading it off `c_header_params`, specifically to avoid
+//! depending on a field that struct doesn't carry in this tree.
+//!
+//! `sputnikvm_new_typed` below calls `VM::pre_warm_access_list`, an entry
+//! point this source drop assumes but does not define: `VM` is declared in
+//! the out-of-tree `evm` crate, and pre-warming the EIP-2929 access sets
+//! (see `access_list::AccessList::pre_warm_address`/`pre_warm_slot`) has to
+//! happen on that side of the boundary, before the first opcode runs. If
+//! `VM` doesn't already expose an equivalent, it needs one:
+//! `fn pre_warm_access_list(&mut self, access_list: Vec<(Address, Vec<U256>)>)`.
+
+#[cfg(not(feature = "std"))] use core::slice;
+#[cfg(feature = "std")] use std::slice;
+
+use bigint::{Address, U256};
+
+use evm::VM;
+
+use crate::common::c_u256;
+use crate::{c_address, c_transaction, c_header_params, sputnikvm_new};
+use crate::dynamic::dynamic_patch_box;
+
+use evm::DynamicPatch;
+
+/// A single EIP-2930 access list entry: an address plus the storage slots
+/// of that address to pre-warm.
+#[repr(C)]
+pub struct c_access_list_item {
+    pub address: c_address,
+    pub storage_keys: *const c_u256,
+    pub storage_keys_length: usize,
+}
+
+/// A type-1 (EIP-2930) transaction: a legacy transaction plus an access list.
+#[repr(C)]
+pub struct c_transaction_2930 {
+    pub transaction: c_transaction,
+    pub access_list: *const c_access_list_item,
+    pub access_list_length: usize,
+}
+
+/// A type-2 (EIP-1559) transaction: a legacy transaction plus an access
+/// list and the fee-market fields `max_fee_per_gas`/`max_priority_fee_per_gas`.
+#[repr(C)]
+pub struct c_transaction_1559 {
+    pub transaction: c_transaction,
+    pub access_list: *const c_access_list_item,
+    pub access_list_length: usize,
+    pub max_fee_per_gas: c_u256,
+    pub max_priority_fee_per_gas: c_u256,
+}
+
+/// Collects the `(address, slots)` pairs out of a raw access list array, to
+/// be used for pre-warming the EIP-2929 access sets.
+unsafe fn collect_access_list(
+    access_list: *const c_access_list_item, access_list_length: usize,
+) -> Vec<(Address, Vec<U256>)> {
+    let items = slice::from_raw_parts(access_list, access_list_length);
+    items.iter().map(|item| {
+        let keys = slice::from_raw_parts(item.storage_keys, item.storage_keys_length);
+        let keys: Vec<U256> = keys.iter().map(|k| (*k).into()).collect();
+        (item.address.into(), keys)
+    }).collect()
+}
+
+/// Computes the effective gas price of an EIP-1559 transaction as
+/// `min(max_fee_per_gas, base_fee + max_priority_fee_per_gas)`, with the
+/// priority tip capped at `max_fee_per_gas - base_fee`.
+fn effective_gas_price(base_fee: U256, max_fee_per_gas: U256, max_priority_fee_per_gas: U256) -> U256 {
+    let max_priority_fee_per_gas = core::cmp::min(
+        max_priority_fee_per_gas,
+        max_fee_per_gas.saturating_sub(base_fee),
+    );
+    core::cmp::min(max_fee_per_gas, base_fee + max_priority_fee_per_gas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pays_base_fee_plus_uncapped_tip() {
+        let price = effective_gas_price(U256::from(100), U256::from(1000), U256::from(10));
+        assert_eq!(price, U256::from(110));
+    }
+
+    #[test]
+    fn caps_tip_at_max_fee_minus_base_fee() {
+        // max_fee_per_gas - base_fee = 50, which is less than the requested tip of 200.
+        let price = effective_gas_price(U256::from(100), U256::from(150), U256::from(200));
+        assert_eq!(price, U256::from(150));
+    }
+
+    #[test]
+    fn never_exceeds_max_fee_per_gas() {
+        let price = effective_gas_price(U256::from(1000), U256::from(150), U256::from(10));
+        assert_eq!(price, U256::from(150));
+    }
+
+    #[test]
+    fn base_fee_above_max_fee_saturates_tip_to_zero() {
+        // base_fee alone already exceeds max_fee_per_gas; the tip must not go negative.
+        let price = effective_gas_price(U256::from(1000), U256::from(150), U256::from(50));
+        assert_eq!(price, U256::from(150));
+    }
+
+    #[test]
+    fn zero_priority_fee_pays_exactly_base_fee() {
+        let price = effective_gas_price(U256::from(100), U256::from(1000), U256::from(0));
+        assert_eq!(price, U256::from(100));
+    }
+}
+
+/// Builds a VM for a typed transaction, pre-warming the EIP-2929 access
+/// sets with the transaction's access list before execution starts.
+fn sputnikvm_new_typed(
+    patch: &DynamicPatch, transaction: c_transaction, header: c_header_params,
+    access_list: Vec<(Address, Vec<U256>)>,
+) -> *mut Box<VM> {
+    let vm = sputnikvm_new(patch, transaction, header);
+    unsafe { (*vm).pre_warm_access_list(access_list) };
+    vm
+}
+
+#[no_mangle]
+pub extern "C" fn sputnikvm_new_access_list(
+    patch: *const dynamic_patch_box, transaction: c_transaction_2930, header: c_header_params,
+) -> *mut Box<VM> {
+    let patch = unsafe { &*(patch as *const DynamicPatch) };
+    let access_list = unsafe { collect_access_list(transaction.access_list, transaction.access_list_length) };
+    sputnikvm_new_typed(patch, transaction.transaction, header, access_list)
+}
+
+#[no_mangle]
+pub extern "C" fn sputnikvm_new_fee_market(
+    patch: *const dynamic_patch_box, mut transaction: c_transaction_1559,
+    header: c_header_params, base_fee: c_u256,
+) -> *mut Box<VM> {
+    let patch = unsafe { &*(patch as *const DynamicPatch) };
+    let access_list = unsafe { collect_access_list(transaction.access_list, transaction.access_list_length) };
+
+    let base_fee: U256 = base_fee.into();
+    let gas_price = effective_gas_price(
+        base_fee,
+        transaction.max_fee_per_gas.into(),
+        transaction.max_priority_fee_per_gas.into(),
+    );
+    transaction.transaction.gas_price = gas_price.into();
+
+    sputnikvm_new_typed(patch, transaction.transaction, header, access_list)
+}